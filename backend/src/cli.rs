@@ -5,4 +5,8 @@ use clap::Parser;
 #[derive(Debug, Clone, PartialEq, Parser)]
 pub struct Args {
     pub data_dir: PathBuf,
+
+    /// delete collected data older than this many days (keeps everything if unset)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub retention_days: Option<u32>,
 }
@@ -2,6 +2,7 @@ mod cli;
 pub mod collect;
 
 use anyhow::{ensure, Context, Ok, Result};
+use chrono::NaiveDateTime;
 use clap::Parser as _;
 use cli::Args;
 use std::{
@@ -18,6 +19,7 @@ use std::{
 };
 
 const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const FILENAME_DATETIME_FORMAT: &str = "%Y_%m_%d__%H_%M_%S_%3f";
 
 struct AbortHandler {
     atom: Arc<AtomicBool>,
@@ -42,19 +44,33 @@ impl AbortHandler {
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    println!("Effective configuration: {args:?}");
     let abort_handler = AbortHandler::new()?;
 
     setup(&args)?;
     while !abort_handler.abort() {
-        collect(&args.data_dir)?;
+        poll(&args, collect);
         sleep(POLL_INTERVAL);
     }
 
     Ok(())
 }
 
-fn collect(data_dir: impl AsRef<Path>) -> Result<()> {
-    let dataset: HashMap<_, _> = [(
+/// One iteration of the main loop, saving what `collect` returns; errors are only logged
+fn poll(args: &Args, collect: impl FnOnce() -> HashMap<&'static str, String>) {
+    // e.g. a full disk shouldn't stop monitoring; we simply try again next time
+    if let Err(e) = save(&args.data_dir, &collect()) {
+        eprintln!("{:#}", e.context("saving collected data"));
+    }
+    if let Some(days) = args.retention_days {
+        if let Err(e) = prune(&args.data_dir, chrono::Duration::days(days.into())) {
+            eprintln!("{:#}", e.context("pruning old data"));
+        }
+    }
+}
+
+fn collect() -> HashMap<&'static str, String> {
+    [(
         "sacct",
         collect::collect_sacct_json().unwrap_or_else(|e| {
             eprintln!("Couldn't collect `sacct`: {e}");
@@ -62,22 +78,59 @@ fn collect(data_dir: impl AsRef<Path>) -> Result<()> {
         }),
     )]
     .into_iter()
-    .collect();
+    .collect()
+}
 
+fn save(data_dir: impl AsRef<Path>, dataset: &HashMap<&str, String>) -> Result<()> {
     for (what, data) in dataset.iter() {
         let filename = data_dir.as_ref().join(gen_filename(what));
-        let mut file = File::create_new(filename)?;
-        file.write_all(data.as_bytes())?;
+        let mut file = File::create_new(&filename)
+            .with_context(|| format!("creating {}", filename.display()))?;
+        file.write_all(data.as_bytes())
+            .with_context(|| format!("writing {}", filename.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Deletes data files whose timestamp is older than `retention`
+fn prune(data_dir: impl AsRef<Path>, retention: chrono::Duration) -> Result<()> {
+    // a retention reaching past the representable range keeps everything
+    let Some(cutoff) = chrono::Local::now()
+        .naive_local()
+        .checked_sub_signed(retention)
+    else {
+        return Ok(());
+    };
+
+    for entry in std::fs::read_dir(data_dir)? {
+        let entry = entry?;
+        // never touch files we didn't write
+        let Some(datetime) = entry.file_name().to_str().and_then(parse_filename) else {
+            continue;
+        };
+
+        if datetime < cutoff && entry.file_type()?.is_file() {
+            let path = entry.path();
+            std::fs::remove_file(&path).with_context(|| format!("deleting {}", path.display()))?;
+            println!("Deleted {} (older than {cutoff})", path.display());
+        }
     }
 
     Ok(())
 }
 
 fn gen_filename(what: &str) -> String {
-    let datetime = chrono::Local::now().format("%Y_%m_%d__%H_%M_%S_%3f");
+    let datetime = chrono::Local::now().format(FILENAME_DATETIME_FORMAT);
     format!("{datetime}__{what}.json")
 }
 
+/// Inverse of `gen_filename`, returns `None` for files not written by us
+fn parse_filename(name: &str) -> Option<NaiveDateTime> {
+    let (datetime, _what) = name.strip_suffix(".json")?.rsplit_once("__")?;
+    NaiveDateTime::parse_from_str(datetime, FILENAME_DATETIME_FORMAT).ok()
+}
+
 fn setup(args: &Args) -> Result<()> {
     // test data dir
     if !args.data_dir.exists() {
@@ -87,3 +140,81 @@ fn setup(args: &Args) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filename_roundtrip() {
+        let name = gen_filename("sacct");
+        let datetime = parse_filename(&name).unwrap();
+        assert!((chrono::Local::now().naive_local() - datetime) < chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn test_parse_foreign_filename() {
+        assert!(parse_filename("notes.txt").is_none());
+        assert!(parse_filename("2024_11_15__sacct.json").is_none());
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_and_foreign_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("2000_01_01__00_00_00_000__sacct.json");
+        let recent = dir.path().join(gen_filename("sacct"));
+        let foreign = dir.path().join("notes.txt");
+        for path in [&old, &recent, &foreign] {
+            File::create_new(path).unwrap();
+        }
+
+        prune(dir.path(), chrono::Duration::days(30)).unwrap();
+
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(foreign.exists());
+    }
+
+    #[test]
+    fn test_prune_huge_retention_keeps_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("2000_01_01__00_00_00_000__sacct.json");
+        File::create_new(&old).unwrap();
+
+        prune(dir.path(), chrono::Duration::days(u32::MAX.into())).unwrap();
+
+        assert!(old.exists());
+    }
+
+    /// stands in for `collect`, so tests don't query the live accounting database
+    fn fake_collect() -> HashMap<&'static str, String> {
+        HashMap::from([("sacct", "{}".to_owned())])
+    }
+
+    #[test]
+    fn test_poll_survives_failing_save() {
+        let dir = tempfile::tempdir().unwrap();
+        // a regular file as data dir makes saving fail, even when running as root
+        let data_dir = dir.path().join("data");
+        File::create_new(&data_dir).unwrap();
+        let args = Args {
+            data_dir: data_dir.clone(),
+            retention_days: Some(1),
+        };
+
+        poll(&args, fake_collect);
+        assert!(data_dir.is_file());
+
+        std::fs::remove_file(&data_dir).unwrap();
+        std::fs::create_dir(&data_dir).unwrap();
+        poll(&args, fake_collect);
+        assert_eq!(std::fs::read_dir(&data_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_zero_retention_rejected() {
+        assert!(Args::try_parse_from(["backend", "data", "--retention-days", "0"]).is_err());
+        let args = Args::try_parse_from(["backend", "data", "--retention-days", "1"]).unwrap();
+        assert_eq!(args.retention_days, Some(1));
+    }
+}
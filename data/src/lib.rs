@@ -1,21 +1,49 @@
-// could look like this:
-//
-// struct SAcct {
-//     jobid: String,
-//     jobidraw: String,
-//     jobname: String,
-//     user: String,
-
-//     elapsed: String, // TODO parse time
-//     state: String,   // TODO maybe parse enum?
-
-//     partition: String,
-//     ntasks: u32,
-//     alloccpus: u32,
-
-//     maxrss: String, // TODO parse units
-//     averss: String, // TODO parse units
-//     avecpu: String, // TODO parse time
-
-//     consumedenergy: f64,
-// }
+pub mod parsing;
+pub mod size;
+
+use chrono::Duration;
+
+use parsing::DurationParseError;
+use size::{FileSize, FileSizeParseError};
+
+/// A single line of `sacct` output
+#[derive(Debug, Clone, PartialEq)]
+pub struct SAcct {
+    pub jobid: String,
+    pub jobidraw: String,
+    pub jobname: String,
+    pub user: String,
+
+    pub elapsed: String,
+    pub state: String, // TODO maybe parse enum?
+
+    pub partition: String,
+    pub ntasks: u32,
+    pub alloccpus: u32,
+
+    pub maxrss: String,
+    pub averss: String,
+    pub avecpu: String,
+
+    pub consumedenergy: f64,
+}
+
+impl SAcct {
+    pub fn elapsed_parsed(&self) -> Result<Duration, DurationParseError> {
+        parsing::slurm_duration(&self.elapsed)
+    }
+
+    pub fn avecpu_parsed(&self) -> Result<Duration, DurationParseError> {
+        parsing::slurm_duration(&self.avecpu)
+    }
+
+    /// `Err(Empty)` for jobs without memory accounting
+    pub fn maxrss_bytes(&self) -> Result<FileSize, FileSizeParseError> {
+        FileSize::parse(&self.maxrss)
+    }
+
+    /// `Err(Empty)` for jobs without memory accounting
+    pub fn averss_bytes(&self) -> Result<FileSize, FileSizeParseError> {
+        FileSize::parse(&self.averss)
+    }
+}
@@ -0,0 +1,192 @@
+use std::num::ParseIntError;
+
+use chrono::Duration;
+use thiserror::Error;
+
+/// Parses a SLURM duration as printed by `sacct` (e.g. `Elapsed`, `AveCPU`).
+///
+/// Accepted forms: `DD-HH:MM:SS`, `HH:MM:SS` and `MM:SS`, each optionally with fractional seconds
+/// (`00:00.003`). Empty means empty after trimming.
+pub fn slurm_duration(input: &str) -> Result<Duration, DurationParseError> {
+    use DurationParseError::*;
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(Empty);
+    }
+    let invalid = || InvalidFormat(input.to_owned());
+
+    let (days, time) = match input.split_once('-') {
+        Some((days, time)) => (Some(parse_component(days, input)?), time),
+        None => (None, input),
+    };
+
+    let parts: Vec<&str> = time.split(':').collect();
+    let (hours, minutes, seconds) = match (days, parts.as_slice()) {
+        (_, [hours, minutes, seconds]) => (parse_component(hours, input)?, *minutes, *seconds),
+        // `MM:SS` is only printed for durations below one hour
+        (None, [minutes, seconds]) => (0, *minutes, *seconds),
+        _ => return Err(invalid()),
+    };
+    // with a day part, sacct never prints 24 hours or more
+    let hours_in_range = days.is_none() || (0..24).contains(&hours);
+    let days = days.unwrap_or(0);
+    let minutes = parse_component(minutes, input)?;
+
+    let (seconds, nanos) = match seconds.split_once('.') {
+        Some((seconds, fraction)) => (
+            parse_component(seconds, input)?,
+            parse_fraction(fraction, input)?,
+        ),
+        None => (parse_component(seconds, input)?, 0),
+    };
+
+    if !hours_in_range || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+        return Err(OutOfRange(input.to_owned()));
+    }
+
+    [
+        Duration::try_days(days),
+        Duration::try_hours(hours),
+        Duration::try_minutes(minutes),
+        Duration::try_seconds(seconds),
+        Some(Duration::nanoseconds(nanos)),
+    ]
+    .into_iter()
+    .try_fold(Duration::zero(), |acc, part| acc.checked_add(&part?))
+    .ok_or_else(|| OutOfRange(input.to_owned()))
+}
+
+/// A non-negative integer; unlike `i64::from_str`, no sign is accepted
+fn parse_component(component: &str, input: &str) -> Result<i64, DurationParseError> {
+    if component.is_empty() || !component.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DurationParseError::InvalidFormat(input.to_owned()));
+    }
+    // only digits are left, so this can only fail by being too large
+    component
+        .parse::<i64>()
+        .map_err(|_| DurationParseError::OutOfRange(input.to_owned()))
+}
+
+/// digits after the decimal point, as nanoseconds
+fn parse_fraction(fraction: &str, input: &str) -> Result<i64, DurationParseError> {
+    if fraction.is_empty() || fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DurationParseError::InvalidFormat(input.to_owned()));
+    }
+    Ok(fraction.parse::<i64>()? * 10i64.pow(9 - fraction.len() as u32))
+}
+
+/// empty means empty after trimming
+#[derive(Debug, Clone, Error)]
+pub enum DurationParseError {
+    #[error("trying to parse an empty string")]
+    Empty,
+    #[error("`{0}` is not of the form [DD-]HH:MM:SS[.fff] or MM:SS[.fff]")]
+    InvalidFormat(String),
+    #[error("`{0}` is out of range (hours after days must be below 24, minutes and seconds below 60, the total must fit a `Duration`)")]
+    OutOfRange(String),
+    #[error("component is not an integer")]
+    InvalidInt(#[from] ParseIntError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duration(days: i64, hours: i64, minutes: i64, seconds: i64, millis: i64) -> Duration {
+        Duration::days(days)
+            + Duration::hours(hours)
+            + Duration::minutes(minutes)
+            + Duration::seconds(seconds)
+            + Duration::milliseconds(millis)
+    }
+
+    #[test]
+    fn test_parse_days() {
+        let parsed = slurm_duration("2-12:30:00").unwrap();
+        assert_eq!(parsed, duration(2, 12, 30, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_hours() {
+        let parsed = slurm_duration("01:02:03").unwrap();
+        assert_eq!(parsed, duration(0, 1, 2, 3, 0));
+        // without a day part, hours don't wrap
+        let parsed = slurm_duration("30:00:00").unwrap();
+        assert_eq!(parsed, duration(0, 30, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_minutes() {
+        let parsed = slurm_duration("05:42").unwrap();
+        assert_eq!(parsed, duration(0, 0, 5, 42, 0));
+    }
+
+    #[test]
+    fn test_parse_fractional_seconds() {
+        let parsed = slurm_duration("00:00.003").unwrap();
+        assert_eq!(parsed, duration(0, 0, 0, 0, 3));
+        let parsed = slurm_duration("1-00:00:01.5").unwrap();
+        assert_eq!(parsed, duration(1, 0, 0, 1, 500));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(matches!(
+            slurm_duration("  "),
+            Err(DurationParseError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_parse_garbage() {
+        assert!(slurm_duration("UNLIMITED").is_err());
+        assert!(slurm_duration("1:2:3:4").is_err());
+        assert!(slurm_duration("1-05:42").is_err());
+        assert!(slurm_duration("00:61").is_err());
+        assert!(slurm_duration("00:00.").is_err());
+        assert!(slurm_duration("00:00.-1").is_err());
+        for input in ["1-25:00:00", "0-24:00:00"] {
+            assert!(
+                matches!(
+                    slurm_duration(input),
+                    Err(DurationParseError::OutOfRange(_))
+                ),
+                "{input}"
+            );
+        }
+        assert!(matches!(
+            slurm_duration("00:00.5x"),
+            Err(DurationParseError::InvalidFormat(input)) if input == "00:00.5x"
+        ));
+    }
+
+    #[test]
+    fn test_parse_sign() {
+        assert!(matches!(
+            slurm_duration("+5:00:00"),
+            Err(DurationParseError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            slurm_duration("00:+5"),
+            Err(DurationParseError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        for input in [
+            "106751991167301-00:00:00",
+            "9999999999999999:00:00",
+            "106751991167-23:59:59",
+            "99999999999999999999-00:00:00",
+        ] {
+            assert!(
+                matches!(
+                    slurm_duration(input),
+                    Err(DurationParseError::OutOfRange(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+}
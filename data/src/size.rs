@@ -0,0 +1,138 @@
+use std::num::ParseIntError;
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileSize(pub usize);
+
+impl FileSize {
+    pub fn as_bytes(&self) -> usize {
+        self.0
+    }
+
+    pub fn as_kib(&self) -> f64 {
+        self.0 as f64 / 1024f64
+    }
+
+    pub fn as_mib(&self) -> f64 {
+        self.0 as f64 / 1024f64.powi(2)
+    }
+
+    pub fn as_gib(&self) -> f64 {
+        self.0 as f64 / 1024f64.powi(3)
+    }
+
+    pub fn as_tib(&self) -> f64 {
+        self.0 as f64 / 1024f64.powi(4)
+    }
+
+    pub fn as_pib(&self) -> f64 {
+        self.0 as f64 / 1024f64.powi(5)
+    }
+
+    pub fn from_bytes(val: usize) -> Self {
+        Self(val)
+    }
+
+    pub fn from_kib(val: usize) -> Self {
+        Self(val * 1024usize)
+    }
+
+    pub fn from_mib(val: usize) -> Self {
+        Self(val * 1024usize.pow(2))
+    }
+
+    pub fn from_gib(val: usize) -> Self {
+        Self(val * 1024usize.pow(3))
+    }
+
+    pub fn from_tib(val: usize) -> Self {
+        Self(val * 1024usize.pow(4))
+    }
+
+    pub fn from_pib(val: usize) -> Self {
+        Self(val * 1024usize.pow(5))
+    }
+
+    pub fn checked_from_kib(val: usize) -> Option<Self> {
+        val.checked_mul(1024usize).map(Self)
+    }
+
+    pub fn checked_from_mib(val: usize) -> Option<Self> {
+        val.checked_mul(1024usize.pow(2)).map(Self)
+    }
+
+    pub fn checked_from_gib(val: usize) -> Option<Self> {
+        val.checked_mul(1024usize.pow(3)).map(Self)
+    }
+
+    pub fn checked_from_tib(val: usize) -> Option<Self> {
+        val.checked_mul(1024usize.pow(4)).map(Self)
+    }
+
+    pub fn checked_from_pib(val: usize) -> Option<Self> {
+        val.checked_mul(1024usize.pow(5)).map(Self)
+    }
+
+    /// empty means empty after trimming
+    pub fn parse(input: &str) -> Result<FileSize, FileSizeParseError> {
+        use FileSizeParseError::*;
+        let input = input.trim();
+
+        fn strip_suffix(s: &str) -> String {
+            s.chars().dropping_back(1).collect()
+        }
+
+        // sacct prints upper case suffixes, but lower case ones show up as well
+        let size = match input.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => FileSize::checked_from_kib(strip_suffix(input).parse()?),
+            Some('M') => FileSize::checked_from_mib(strip_suffix(input).parse()?),
+            Some('G') => FileSize::checked_from_gib(strip_suffix(input).parse()?),
+            Some('T') => FileSize::checked_from_tib(strip_suffix(input).parse()?),
+            Some('P') => FileSize::checked_from_pib(strip_suffix(input).parse()?),
+            None => return Err(Empty),
+            _ => Some(FileSize::from_bytes(input.parse()?)),
+        };
+        size.ok_or_else(|| Overflow(input.to_owned()))
+    }
+}
+
+/// empty means empty after trimming
+#[derive(Debug, Clone, Error)]
+pub enum FileSizeParseError {
+    #[error("trying to parse an empty string")]
+    Empty,
+    #[error("scalar part is not an usize")]
+    InvalidInt(#[from] ParseIntError),
+    #[error("`{0}` does not fit into an usize")]
+    Overflow(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lowercase_suffix() {
+        assert_eq!(FileSize::parse("10k").unwrap(), FileSize::from_kib(10));
+        assert_eq!(FileSize::parse("3m").unwrap(), FileSize::from_mib(3));
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        assert!(matches!(
+            FileSize::parse("20000P"),
+            Err(FileSizeParseError::Overflow(_))
+        ));
+        assert_eq!(FileSize::parse("1P").unwrap(), FileSize::from_pib(1));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(matches!(
+            FileSize::parse(""),
+            Err(FileSizeParseError::Empty)
+        ));
+    }
+}
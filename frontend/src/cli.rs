@@ -1,9 +1,34 @@
-use std::path::PathBuf;
+use std::{net::IpAddr, path::PathBuf};
 
+use chrono::NaiveDateTime;
 use clap::Parser;
+use serde::Serialize;
 
-#[derive(Debug, Clone, PartialEq, Parser)]
+#[derive(Debug, Clone, PartialEq, Parser, Serialize)]
 pub struct Args {
     #[arg(long)]
     pub data_dir: PathBuf,
+
+    /// address to listen on
+    #[arg(long, default_value = "0.0.0.0")]
+    pub bind: IpAddr,
+
+    #[arg(long, default_value_t = 3333)]
+    pub port: u16,
+
+    /// file with one regex per line; matches in job names and users get redacted
+    #[arg(long)]
+    pub redaction_list: Option<PathBuf>,
+
+    /// pretend it is this point in time (e.g. `2024-06-01T12:00:00`) when windowing data; for demos
+    #[arg(long)]
+    pub fake_now: Option<NaiveDateTime>,
+
+    /// how often to retry reading a data file on transient I/O errors
+    #[arg(long, default_value_t = 3)]
+    pub read_retries: u32,
+
+    /// seconds after which a single read attempt of a data file is aborted
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub read_timeout: u64,
 }
@@ -1,6 +1,16 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
 use anyhow::{Context as _, Result};
 use chrono::NaiveDateTime;
 
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
 pub fn datetime_from_filename(name: &str) -> Result<NaiveDateTime> {
     const FORMAT: &str = "%Y_%m_%d__%H_%M_%S";
     const RESULTING_FORMAT_LEN: usize = "yyyy_mm_dd__hh_mm_ss".len();
@@ -12,5 +22,124 @@ pub fn datetime_from_filename(name: &str) -> Result<NaiveDateTime> {
         .take(RESULTING_FORMAT_LEN)
         .collect();
     NaiveDateTime::parse_from_str(datetime_part.as_str(), FORMAT)
-            .with_context(|| format!("parsing datetime from {name}"))
+        .with_context(|| format!("parsing datetime from {name}"))
+}
+
+/// Reads a file, retrying transient errors (our data dir lives on a flaky NFS mount).
+///
+/// Each attempt is aborted after `timeout`, so a hanging mount doesn't block loading forever.
+pub fn read_to_string_retrying(
+    path: impl AsRef<Path>,
+    retries: u32,
+    timeout: Duration,
+) -> io::Result<String> {
+    let path = path.as_ref();
+    retry(retries, READ_RETRY_BACKOFF, path, || {
+        read_to_string_with_timeout(path.to_owned(), timeout)
+    })
+}
+
+fn read_to_string_with_timeout(path: PathBuf, timeout: Duration) -> io::Result<String> {
+    let (tx, rx) = mpsc::channel();
+    // the thread is leaked if the read hangs, but at least we don't hang with it
+    thread::spawn(move || {
+        let _ = tx.send(fs::read_to_string(path));
+    });
+
+    rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("read timed out after {timeout:?}"),
+        ))
+    })
+}
+
+fn retry<T>(
+    retries: u32,
+    backoff: Duration,
+    path: &Path,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if is_transient(&e) && attempt < retries => {
+                attempt += 1;
+                eprintln!(
+                    "reading {}: {e} (retry {attempt}/{retries})",
+                    path.display()
+                );
+                thread::sleep(backoff);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// anything but errors that won't go away by trying again
+///
+/// Timeouts count as permanent: each one leaves a thread hanging on the mount, retrying would
+/// only pile up more of them.
+fn is_transient(error: &io::Error) -> bool {
+    use io::ErrorKind::*;
+    !matches!(
+        error.kind(),
+        NotFound | PermissionDenied | InvalidData | InvalidInput | TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_recovers_from_transient_error() {
+        let mut attempts = 0;
+        let result = retry(3, Duration::ZERO, Path::new("test"), || {
+            attempts += 1;
+            match attempts {
+                1 => Err(io::Error::from(io::ErrorKind::Interrupted)),
+                _ => Ok("content"),
+            }
+        });
+
+        assert_eq!(result.unwrap(), "content");
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut attempts = 0;
+        let result: io::Result<()> = retry(2, Duration::ZERO, Path::new("test"), || {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::Interrupted))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_no_retry_on_permanent_error() {
+        let mut attempts = 0;
+        let result: io::Result<()> = retry(3, Duration::ZERO, Path::new("test"), || {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_no_retry_on_timeout() {
+        let mut attempts = 0;
+        let result: io::Result<()> = retry(3, Duration::ZERO, Path::new("test"), || {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::TimedOut))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
 }
@@ -2,25 +2,28 @@
 
 mod cli;
 mod data;
+mod metric;
 mod parse;
+mod redact;
 mod render;
 
 // TODO block more warnings, maybe here (in dev), maybe in pipeline
 
 use std::{
-    collections::HashMap,
-    fs::{self, DirEntry, FileType},
+    fs::{DirEntry, FileType},
     io::Cursor,
+    net::SocketAddr,
     ops::Deref,
     path::Path,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
 use axum::{
+    extract,
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::get,
-    Router,
+    Json, Router,
 };
 use axum_macros::debug_handler;
 use base64ct::{Base64, Encoding as _};
@@ -28,15 +31,33 @@ use chrono::{Duration, Local, NaiveDateTime};
 use clap::Parser;
 use image::{ImageFormat, RgbImage};
 use itertools::Itertools as _;
-use maud::{html, Markup};
-use once_cell::sync::Lazy;
+use maud::{html, Markup, PreEscaped};
+use once_cell::sync::{Lazy, OnceCell};
+use plotters::prelude::SVGBackend;
+use serde::Deserialize;
+use slurm_monitor_data::parsing::slurm_duration;
 use tokio::sync::RwLock;
 
+use metric::Metric;
+use redact::Redactor;
 use render::plot;
 
+const CHART_SIZE: (u32, u32) = (800, 600);
+/// upper bound for `?hours=` (10 years)
+const CHART_MAX_HOURS: u32 = 24 * 365 * 10;
+
 const SACCT_HEADER_JOBID: &str = "JobID";
+const SACCT_HEADER_JOBNAME: &str = "JobName";
+const SACCT_HEADER_USER: &str = "User";
+const SACCT_HEADER_STATE: &str = "State";
+const SACCT_HEADER_ALLOCCPUS: &str = "AllocCPUS";
+const SACCT_HEADER_ELAPSED: &str = "Elapsed";
 
 static DATA_SACCT: Lazy<RwLock<Vec<(NaiveDateTime, String)>>> = Lazy::new(|| RwLock::new(vec![]));
+/// effective configuration, set once at startup
+static ARGS: OnceCell<cli::Args> = OnceCell::new();
+/// compiled once at startup from `--redaction-list`
+static REDACTOR: OnceCell<Redactor> = OnceCell::new();
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -44,13 +65,28 @@ async fn main() -> Result<()> {
     // tracing_subscriber::fmt::init();
 
     let args = cli::Args::parse();
+    println!("Effective configuration: {args:?}");
 
-    let sacct_data_local = load_sacct(&args.data_dir)?;
+    let sacct_data_local = load_sacct(
+        &args.data_dir,
+        args.read_retries,
+        std::time::Duration::from_secs(args.read_timeout),
+    )?;
     let (data, errors): (Vec<_>, Vec<_>) = sacct_data_local.into_iter().partition_result();
     errors
         .into_iter()
         .for_each(|e| eprintln!("{:#}", e.context("parsing sacct data")));
     *DATA_SACCT.deref().write().await = data;
+    let redactor = match &args.redaction_list {
+        Some(path) => Redactor::load(path)?,
+        None => Redactor::default(),
+    };
+    REDACTOR
+        .set(redactor)
+        .map_err(|_| anyhow!("redactor was already initialized"))?;
+    let address = SocketAddr::new(args.bind, args.port);
+    ARGS.set(args)
+        .map_err(|_| anyhow!("configuration was already initialized"))?;
 
     // build our application with a route
     /*let x = || async {
@@ -61,16 +97,10 @@ async fn main() -> Result<()> {
             }),
         }
     };*/
-    let app = Router::new()
-        // `GET /` goes to `root`
-        .route("/", get(index));
-
-    // run our app with hyper, listening globally on port 3333
-    // TODO make port an argument
-    let port: u16 = 3333;
-    let address = format!("0.0.0.0:{port}");
+    let app = app();
 
-    let listener = tokio::net::TcpListener::bind(&address).await.unwrap();
+    // run our app with hyper, by default listening globally on port 3333
+    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
     println!("Serving http://{address}");
 
     axum::serve(listener, app).await.unwrap();
@@ -78,7 +108,11 @@ async fn main() -> Result<()> {
 }
 
 // TODO move this to data, and abstract over datasets
-fn load_sacct(data_dir: impl AsRef<Path>) -> Result<Vec<Result<(NaiveDateTime, String)>>> {
+fn load_sacct(
+    data_dir: impl AsRef<Path>,
+    read_retries: u32,
+    read_timeout: std::time::Duration,
+) -> Result<Vec<Result<(NaiveDateTime, String)>>> {
     fn metadata(entry: &DirEntry) -> Result<(String, FileType)> {
         let name = entry.file_name().to_string_lossy().into_owned();
 
@@ -112,8 +146,8 @@ fn load_sacct(data_dir: impl AsRef<Path>) -> Result<Vec<Result<(NaiveDateTime, S
                 return Ok(None);
             }
 
-            let content =
-                fs::read_to_string(entry.path()).with_context(|| format!("reading {filename}"))?;
+            let content = data::read_to_string_retrying(entry.path(), read_retries, read_timeout)
+                .with_context(|| format!("reading {filename}"))?;
             let datetime = data::datetime_from_filename(&filename)?;
 
             Result::Ok(Some((datetime, content)))
@@ -132,16 +166,35 @@ fn load_sacct(data_dir: impl AsRef<Path>) -> Result<Vec<Result<(NaiveDateTime, S
 
 // (from: https://github.com/tokio-rs/axum/blob/main/examples/anyhow-error-response/src/main.rs)
 // Make our own error that wraps `anyhow::Error`.
-struct AppError(anyhow::Error);
+struct AppError {
+    status: StatusCode,
+    error: anyhow::Error,
+}
+
+impl AppError {
+    /// Invalid user input, e.g. query parameters
+    fn bad_request(error: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error,
+        }
+    }
+}
 
 // Tell axum how to convert `AppError` into a response.
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
-        )
-            .into_response()
+        (self.status, error_page(self.status, &self.error)).into_response()
+    }
+}
+
+/// Page shown for every failed handler
+fn error_page(status: StatusCode, error: &anyhow::Error) -> Markup {
+    html! {
+        h1 { (status) }
+        h3 style="color: red" { "Something went wrong:" }
+        pre style="color: red" { (format!("{error:#}")) }
+        a href="/" { "Back to overview" }
     }
 }
 
@@ -152,31 +205,122 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: err.into(),
+        }
     }
 }
 
-#[debug_handler]
-// basic handler that responds with a static string
-async fn index() -> Markup {
-    // TODO update instead of taking only last
+/// Returns the effective configuration as JSON
+async fn config() -> Result<Json<&'static cli::Args>, AppError> {
+    let args = ARGS
+        .get()
+        .ok_or_else(|| anyhow!("configuration not initialized yet"))?;
+    Ok(Json(args))
+}
 
-    let jobcount_chart = make_jobcount_48h_chart();
+fn app() -> Router {
+    Router::new()
+        // `GET /` goes to `root`
+        .route("/", get(index))
+        .route("/charts/:metric", get(metric_chart))
+        .route("/tables/jobs", get(jobs_table))
+        .route("/config", get(config))
+}
 
+#[debug_handler]
+// landing page linking to all views
+async fn index() -> Markup {
     html! {
-        h1 { "Working!" }
-        h2 { "Here be monitors…" }
-        @match jobcount_chart.await {
-            Ok(data) => img src=(format!("data:image/png;base64,{data}", data=Base64::encode_string(&data))) {},
-            Err(e) => h3 style="color: red" { (e) },
+        h1 { "Slurm monitor" }
+        ul {
+            @for metric in Metric::ALL {
+                li { a href=(format!("/charts/{}", metric.name())) { (metric.description()) " (last 48h)" } }
+            }
+            li { a href="/tables/jobs" { "Running jobs" } }
         }
+    }
+}
 
-        //p { "DEBUG" (format!("{:?}", data.clone().map(|d| d.map(|d| &d["jobs"]))))}
-        @match sacct_table().await {
-            Ok(data) => (data),
-            Err(e) => h3 style="color: red" { (e) },
-        }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ChartFormat {
+    /// base64-inlined into an `<img>`
+    #[default]
+    Png,
+    /// inlined directly as `<svg>`
+    Svg,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartQuery {
+    #[serde(default)]
+    format: ChartFormat,
+    /// size of the time window, counting back from now (`1..=CHART_MAX_HOURS`)
+    #[serde(default = "ChartQuery::default_hours")]
+    hours: u32,
+    /// only count jobs that ran at least this long, in `sacct` format (e.g. `05:00`); jobcount only
+    min_duration: Option<String>,
+}
+
+impl ChartQuery {
+    fn default_hours() -> u32 {
+        48
+    }
+}
+
+async fn metric_chart(
+    extract::Path(metric): extract::Path<Metric>,
+    extract::Query(query): extract::Query<ChartQuery>,
+) -> Result<Markup, AppError> {
+    if !(1..=CHART_MAX_HOURS).contains(&query.hours) {
+        return Err(AppError::bad_request(anyhow!(
+            "`hours` must be between 1 and {CHART_MAX_HOURS}"
+        )));
     }
+    let min_duration = match query.min_duration.as_deref() {
+        None => None,
+        Some(_) if metric != Metric::JobCount => {
+            return Err(AppError::bad_request(anyhow!(
+                "`min_duration` is only supported for {}",
+                Metric::JobCount.name()
+            )));
+        }
+        Some(min_duration) => Some(
+            slurm_duration(min_duration)
+                .context("parsing `min_duration`")
+                .map_err(AppError::bad_request)?,
+        ),
+    };
+    let window = Duration::hours(query.hours.into());
+    let now = now(ARGS.get().and_then(|args| args.fake_now));
+    let dataset = metric_dataset(metric, window, now, min_duration).await?;
+
+    Ok(match query.format {
+        ChartFormat::Png => {
+            let data = render_png_chart(metric, window, &dataset)?;
+            html! {
+                img src=(format!("data:image/png;base64,{data}", data=Base64::encode_string(&data))) {}
+            }
+        }
+        ChartFormat::Svg => {
+            let (x, y) = CHART_SIZE;
+            let mut svg = String::new();
+            plot::metric_over_time(
+                SVGBackend::with_string(&mut svg, (x, y)),
+                &metric.title(window),
+                plot::x_step(window),
+                dataset.as_slice(),
+            )?;
+            html! { (PreEscaped(svg)) }
+        }
+    })
+}
+
+async fn jobs_table() -> Result<Markup, AppError> {
+    // TODO update instead of taking only last
+    Ok(sacct_table().await?)
 }
 
 /*fn make_chart<DB, CT>(title: impl AsRef<str>, dataset: ChartContext<'_, DB, CT>) -> Result<Vec<u8>>
@@ -201,53 +345,69 @@ where
     Ok(output_buf)
 }*/
 
-async fn make_jobcount_48h_chart() -> Result<Vec<u8>> {
-    fn is_main_job(id: impl AsRef<str>) -> bool {
-        !id.as_ref().contains('.')
-    }
+/// Current time used for windowing, `fake_now` comes from `--fake-now`
+fn now(fake_now: Option<NaiveDateTime>) -> NaiveDateTime {
+    fake_now.unwrap_or_else(|| Local::now().naive_local())
+}
 
-    fn get_id(job: &HashMap<String, String>) -> Result<String> {
-        job.get(SACCT_HEADER_JOBID)
-            .ok_or_else(|| {
-                anyhow!("Data inconsistency at '{job:?}': `{SACCT_HEADER_JOBID}` not found")
-            })
-            .map(String::from)
-    }
+/// `(now - window, now]`; snapshots after a faked `now` are left out
+fn in_window(datetime: NaiveDateTime, now: NaiveDateTime, window: Duration) -> bool {
+    // a window reaching past the representable range includes everything
+    datetime <= now
+        && now
+            .checked_sub_signed(window)
+            .is_none_or(|start| datetime > start)
+}
+
+async fn metric_dataset(
+    metric: Metric,
+    window: Duration,
+    now: NaiveDateTime,
+    min_duration: Option<Duration>,
+) -> Result<Vec<(NaiveDateTime, f64)>> {
     let data = DATA_SACCT.read().await;
     if data.is_empty() {
         Err(anyhow!("no datasets found"))?;
     };
 
-    let dataset = data
-        .iter()
-        .filter(|(datetime, _)| *datetime > Local::now().naive_local() - Duration::hours(48))
-        .map(|(datetime, content)| parse::sacct_csvlike(content).map(|data| (*datetime, data)))
-        .map_ok(|(datetime, (header, data))| {
-            let jobid_key = header.iter().any(|s| *s == SACCT_HEADER_JOBID);
-            if !jobid_key {
-                bail!("Dataset contains no job ids!");
-            };
-
-            let job_ids = data
-                .into_iter()
-                .map(|j| j.and_then(|j| get_id(&j)))
-                .filter(|j| j.is_err() || j.as_ref().is_ok_and(is_main_job)); // TODO why tf are map_ok and filter_ok not working ?!?
-            let job_count = job_ids.process_results(|jobs| jobs.count());
+    evaluate_window(metric, &data, window, now, min_duration)
+}
 
-            job_count.map(|count| (datetime, count))
+/// Evaluates `metric` for every snapshot in the `window` before `now`
+fn evaluate_window(
+    metric: Metric,
+    data: &[(NaiveDateTime, String)],
+    window: Duration,
+    now: NaiveDateTime,
+    min_duration: Option<Duration>,
+) -> Result<Vec<(NaiveDateTime, f64)>> {
+    data.iter()
+        .filter(|(datetime, _)| in_window(*datetime, now, window))
+        .map(|(datetime, content)| {
+            let (header, jobs) = parse::sacct_csvlike(content)?;
+            metric
+                .evaluate(&header, jobs, min_duration)
+                .map(|value| (*datetime, value))
         })
-        .flatten()
-        .process_results(|x| x.collect_vec())?;
+        .process_results(|x| x.collect_vec())
+}
 
-    let (x, y) = (800u32, 600u32);
+fn render_png_chart(
+    metric: Metric,
+    window: Duration,
+    dataset: &[(NaiveDateTime, f64)],
+) -> Result<Vec<u8>> {
+    let (x, y) = CHART_SIZE;
     let mut buf = vec![];
-    plot::jobcount_over_time(
+    plot::metric_over_time(
         render::create_bitmap_buffer(&mut buf, x, y),
-        dataset.as_slice(),
+        &metric.title(window),
+        plot::x_step(window),
+        dataset,
     )?;
     let image = RgbImage::from_raw(x, y, buf) // TODO there was a more compact way of loading raw images (maybe directly creating a buffer via images crate). Look it up in docs.
         .ok_or_else(|| anyhow!("failed to create image from internal buffer (too small?)"))
-        .context("rendering job graph")?;
+        .with_context(|| format!("rendering {} chart", metric.name()))?;
 
     let mut output_buf: Vec<u8> = Vec::new();
     image.write_to(&mut Cursor::new(&mut output_buf), ImageFormat::Png)?;
@@ -276,15 +436,15 @@ async fn sacct_table() -> Result<Markup> {
         "consumedenergy",
     ];
 
+    let redactor = REDACTOR
+        .get()
+        .ok_or_else(|| anyhow!("redactor not initialized yet"))?;
     let data = DATA_SACCT.read().await;
     let Some(data) = data.last() else {
         bail!("Somehow global DATA_SACCT seems to be empty");
     };
-    let (header, data) = match parse::sacct_csvlike(&data.1) {
-        // TODO somehow I thought parsing the csv in every function would be better than (asyncly) one-time at startup -__-. Fix this.
-        Ok(data) => data,
-        Err(_) => todo!(),
-    };
+    // TODO somehow I thought parsing the csv in every function would be better than (asyncly) one-time at startup -__-. Fix this.
+    let (header, data) = parse::sacct_csvlike(&data.1)?;
 
     Ok(html! {
         table {
@@ -299,6 +459,7 @@ async fn sacct_table() -> Result<Markup> {
                         anyhow::Result::Ok(line) => tr {
                             @for key in header.iter() {
                                 @match &line.get(key) {
+                                    Some(val) if key == SACCT_HEADER_JOBNAME || key == SACCT_HEADER_USER => td { (redactor.redact(val)) },
                                     Some(val) => td { (val) },
                                     None => td style="color: red" { "ERROR" }
                                 }
@@ -311,3 +472,187 @@ async fn sacct_table() -> Result<Markup> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+    };
+    use tower::ServiceExt as _;
+
+    use super::*;
+
+    async fn request(route: &str) -> Response {
+        app()
+            .oneshot(Request::builder().uri(route).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Fills the globals `main` would set up; shared by all tests, so the data is always the same
+    async fn seed() {
+        const SACCT: &str = "\
+JobID|JobName|User|State|AllocCPUS|Elapsed
+1|train|alice|RUNNING|4|01:00:00
+1.batch|batch|alice|RUNNING|4|01:00:00
+2|eval|bob|PENDING|8|00:00:00";
+
+        let now = Local::now().naive_local();
+        *DATA_SACCT.write().await = (1..=3)
+            .map(|i| (now - Duration::hours(i), SACCT.to_owned()))
+            .collect();
+        REDACTOR.get_or_init(Redactor::default);
+    }
+
+    #[tokio::test]
+    async fn test_index_responds() {
+        assert_eq!(request("/").await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_routes_respond() {
+        seed().await;
+        for route in [
+            "/charts/jobcount",
+            "/charts/alloccpus",
+            "/charts/jobcount?format=svg&hours=72",
+            "/charts/jobcount?min_duration=05:00",
+            "/tables/jobs",
+        ] {
+            assert_eq!(request(route).await.status(), StatusCode::OK, "{route}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_min_duration_is_bad_request() {
+        for route in [
+            "/charts/jobcount?min_duration=soon",
+            "/charts/jobcount?min_duration=-5:00",
+            "/charts/alloccpus?min_duration=05:00",
+        ] {
+            assert_eq!(
+                request(route).await.status(),
+                StatusCode::BAD_REQUEST,
+                "{route}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_config_responds() {
+        ARGS.get_or_init(|| {
+            cli::Args::parse_from(["frontend", "--data-dir", "data", "--port", "8080"])
+        });
+        let response = request("/config").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let config: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(config["data_dir"], "data");
+        assert_eq!(config["bind"], "0.0.0.0");
+        assert_eq!(config["port"], 8080);
+        assert_eq!(config["read_retries"], 3);
+        assert_eq!(config["fake_now"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_failing_handler_renders_error_page() {
+        let response = request("/charts/jobcount?hours=0").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("400 Bad Request"));
+        assert!(body.contains("Something went wrong:"));
+        assert!(body.contains("`hours` must be between"));
+        assert!(body.contains("Back to overview"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_hours_is_bad_request() {
+        for hours in [0, CHART_MAX_HOURS + 1, u32::MAX] {
+            let response = request(&format!("/charts/jobcount?hours={hours}")).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "hours={hours}");
+
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.contains("400 Bad Request"));
+            assert!(body.contains("`hours` must be between"));
+        }
+    }
+
+    #[test]
+    fn test_render_svg_chart() {
+        let sacct = "JobID|State|AllocCPUS\n1|RUNNING|4\n2|PENDING|8";
+        let start = NaiveDateTime::parse_from_str("2020-01-01 00:00:00", "%F %T").unwrap();
+        for metric in Metric::ALL {
+            let dataset = (0..3)
+                .map(|i| {
+                    let (header, jobs) = parse::sacct_csvlike(sacct).unwrap();
+                    let value = metric.evaluate(&header, jobs, None).unwrap();
+                    (start + Duration::hours(i), value)
+                })
+                .collect_vec();
+
+            let window = Duration::hours(48);
+            let mut svg = String::new();
+            plot::metric_over_time(
+                SVGBackend::with_string(&mut svg, CHART_SIZE),
+                &metric.title(window),
+                plot::x_step(window),
+                &dataset,
+            )
+            .unwrap();
+            assert!(svg.starts_with("<svg"));
+            assert!(svg.contains(&metric.title(window)));
+        }
+    }
+
+    #[test]
+    fn test_fake_now_selects_window() {
+        let fake_now = NaiveDateTime::parse_from_str("2020-01-10 00:00:00", "%F %T").unwrap();
+        // one snapshot every 12h, from 4 days before to 1 day after `fake_now`
+        let data = (-8..=2)
+            .map(|i| {
+                let datetime = fake_now + Duration::hours(12 * i);
+                (datetime, "JobID\n1\n2".to_owned())
+            })
+            .collect_vec();
+
+        let now = now(Some(fake_now));
+        assert_eq!(now, fake_now);
+        let dataset =
+            evaluate_window(Metric::JobCount, &data, Duration::hours(48), now, None).unwrap();
+        let datetimes = dataset.iter().map(|(datetime, _)| *datetime).collect_vec();
+        let expected = (-3..=0)
+            .map(|i| fake_now + Duration::hours(12 * i))
+            .collect_vec();
+        assert_eq!(datetimes, expected);
+        assert!(dataset.iter().all(|(_, value)| *value == 2.0));
+    }
+
+    #[test]
+    fn test_zero_read_timeout_rejected() {
+        let args = ["frontend", "--data-dir", "data", "--read-timeout"];
+        assert!(cli::Args::try_parse_from(args.iter().chain(&["0"])).is_err());
+        let parsed = cli::Args::try_parse_from(args.iter().chain(&["1"])).unwrap();
+        assert_eq!(parsed.read_timeout, 1);
+    }
+
+    #[test]
+    fn test_window_anchors_on_now() {
+        let now = NaiveDateTime::parse_from_str("2020-01-03 12:00:00", "%F %T").unwrap();
+        let inside = NaiveDateTime::parse_from_str("2020-01-02 12:00:00", "%F %T").unwrap();
+        let outside = NaiveDateTime::parse_from_str("2020-01-01 11:00:00", "%F %T").unwrap();
+
+        assert!(in_window(inside, now, Duration::hours(48)));
+        assert!(!in_window(outside, now, Duration::hours(48)));
+        assert!(in_window(outside, now, Duration::MAX));
+        assert!(!in_window(
+            now + Duration::hours(1),
+            now,
+            Duration::hours(48)
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use chrono::Duration;
+use itertools::Itertools as _;
+use serde::Deserialize;
+use slurm_monitor_data::parsing::slurm_duration;
+
+use crate::{SACCT_HEADER_ALLOCCPUS, SACCT_HEADER_ELAPSED, SACCT_HEADER_JOBID, SACCT_HEADER_STATE};
+
+/// A value computed from a single `sacct` snapshot, to be plotted over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// number of jobs, not counting job steps
+    JobCount,
+    /// CPUs allocated to running jobs
+    AllocCpus,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 2] = [Metric::JobCount, Metric::AllocCpus];
+
+    /// as used in `/charts/:metric`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::JobCount => "jobcount",
+            Metric::AllocCpus => "alloccpus",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Metric::JobCount => "Job count",
+            Metric::AllocCpus => "Allocated CPUs",
+        }
+    }
+
+    /// chart title for data from the last `window`, e.g. `Jobcount_(last_48h)`
+    pub fn title(&self, window: Duration) -> String {
+        let name = match self {
+            Metric::JobCount => "Jobcount",
+            Metric::AllocCpus => "Allocated_CPUs",
+        };
+        let hours = window.num_hours();
+        // whole days read better beyond two of them
+        let window = if hours > 48 && hours % 24 == 0 {
+            format!("{}d", hours / 24)
+        } else {
+            format!("{hours}h")
+        };
+
+        format!("{name}_(last_{window})")
+    }
+
+    /// `sacct` columns needed by [`Metric::evaluate`]
+    pub fn required_columns(&self) -> &'static [&'static str] {
+        match self {
+            Metric::JobCount => &[SACCT_HEADER_JOBID],
+            Metric::AllocCpus => &[
+                SACCT_HEADER_JOBID,
+                SACCT_HEADER_STATE,
+                SACCT_HEADER_ALLOCCPUS,
+            ],
+        }
+    }
+
+    /// `min_duration` leaves out jobs with a shorter `Elapsed`. For jobs that are still running
+    /// that is their runtime so far, so they count as soon as they have run long enough.
+    pub fn evaluate(
+        &self,
+        header: &[String],
+        jobs: Vec<Result<HashMap<String, String>>>,
+        min_duration: Option<Duration>,
+    ) -> Result<f64> {
+        let missing = self
+            .required_columns()
+            .iter()
+            .chain(min_duration.map(|_| &SACCT_HEADER_ELAPSED))
+            .filter(|column| !header.iter().any(|s| s == *column))
+            .join("`, `");
+        if !missing.is_empty() {
+            bail!(
+                "Dataset lacks column(s) `{missing}` needed for {}",
+                self.name()
+            );
+        };
+
+        let main_jobs = jobs
+            .into_iter()
+            .map(|j| j.and_then(|j| Ok((is_main_job(get_id(&j)?) && lasts(&j, min_duration)?, j))))
+            .filter(|j| !matches!(j, Ok((false, _))))
+            .map_ok(|(_, j)| j);
+
+        match self {
+            Metric::JobCount => main_jobs.process_results(|jobs| jobs.count() as f64),
+            Metric::AllocCpus => main_jobs
+                .map(|j| {
+                    let j = j?;
+                    if get(&j, SACCT_HEADER_STATE)? != "RUNNING" {
+                        return Ok(0);
+                    }
+                    let cpus = get(&j, SACCT_HEADER_ALLOCCPUS)?;
+                    cpus.parse::<u32>()
+                        .with_context(|| format!("parsing `{SACCT_HEADER_ALLOCCPUS}` ({cpus})"))
+                })
+                .process_results(|cpus| cpus.sum::<u32>() as f64),
+        }
+    }
+}
+
+fn is_main_job(id: impl AsRef<str>) -> bool {
+    !id.as_ref().contains('.')
+}
+
+/// whether `job` ran for at least `min_duration` (always true without one)
+fn lasts(job: &HashMap<String, String>, min_duration: Option<Duration>) -> Result<bool> {
+    let Some(min_duration) = min_duration else {
+        return Ok(true);
+    };
+    let elapsed = get(job, SACCT_HEADER_ELAPSED)?;
+    let elapsed = slurm_duration(elapsed)
+        .with_context(|| format!("parsing `{SACCT_HEADER_ELAPSED}` ({elapsed})"))?;
+    Ok(elapsed >= min_duration)
+}
+
+fn get_id(job: &HashMap<String, String>) -> Result<&str> {
+    get(job, SACCT_HEADER_JOBID)
+}
+
+fn get<'a>(job: &'a HashMap<String, String>, key: &str) -> Result<&'a str> {
+    job.get(key).map(String::as_str).ok_or_else(|| {
+        let id = job
+            .get(SACCT_HEADER_JOBID)
+            .map_or("<unknown>", String::as_str);
+        anyhow!("Data inconsistency at job {id}: `{key}` not found")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SACCT: &str = "\
+JobID|State|AllocCPUS
+1|RUNNING|4
+1.batch|RUNNING|4
+2|RUNNING|8
+3|PENDING|16";
+
+    fn evaluate(metric: Metric) -> f64 {
+        let (header, jobs) = parse::sacct_csvlike(SACCT).unwrap();
+        metric.evaluate(&header, jobs, None).unwrap()
+    }
+
+    #[test]
+    fn test_jobcount() {
+        assert_eq!(evaluate(Metric::JobCount), 3.0);
+    }
+
+    #[test]
+    fn test_alloccpus() {
+        assert_eq!(evaluate(Metric::AllocCpus), 12.0);
+    }
+
+    #[test]
+    fn test_min_duration() {
+        // a running job counts with its runtime so far, pending ones haven't run at all
+        let sacct = "\
+JobID|State|Elapsed
+1|COMPLETED|00:00:01
+1.batch|COMPLETED|00:00:01
+2|COMPLETED|01:00:00
+3|RUNNING|00:04:59
+4|RUNNING|1-00:00:00
+5|PENDING|00:00:00";
+        let jobcount = |min_duration| {
+            let (header, jobs) = parse::sacct_csvlike(sacct).unwrap();
+            Metric::JobCount
+                .evaluate(&header, jobs, min_duration)
+                .unwrap()
+        };
+
+        assert_eq!(jobcount(None), 5.0);
+        assert_eq!(jobcount(Some(Duration::zero())), 5.0);
+        assert_eq!(jobcount(Some(Duration::minutes(5))), 2.0);
+        assert_eq!(jobcount(Some(Duration::days(2))), 0.0);
+    }
+
+    #[test]
+    fn test_min_duration_needs_elapsed() {
+        let (header, jobs) = parse::sacct_csvlike(SACCT).unwrap();
+        let error = Metric::JobCount
+            .evaluate(&header, jobs, Some(Duration::minutes(5)))
+            .unwrap_err();
+        assert!(error.to_string().contains("`Elapsed`"));
+    }
+
+    #[test]
+    fn test_missing_columns() {
+        let (header, jobs) = parse::sacct_csvlike("JobID|State\n1|RUNNING").unwrap();
+        let error = Metric::AllocCpus.evaluate(&header, jobs, None).unwrap_err();
+        assert!(error.to_string().contains("`AllocCPUS`"));
+
+        let (header, jobs) = parse::sacct_csvlike("State\nRUNNING").unwrap();
+        let error = Metric::JobCount.evaluate(&header, jobs, None).unwrap_err();
+        assert!(error.to_string().contains("`JobID`"));
+    }
+
+    #[test]
+    fn test_missing_value_names_job() {
+        let job = HashMap::from([(SACCT_HEADER_JOBID.to_owned(), "42".to_owned())]);
+        let error = get(&job, SACCT_HEADER_STATE).unwrap_err().to_string();
+        assert_eq!(error, "Data inconsistency at job 42: `State` not found");
+    }
+
+    #[test]
+    fn test_title() {
+        let title = Metric::JobCount.title(Duration::hours(48));
+        assert_eq!(title, "Jobcount_(last_48h)");
+        let title = Metric::AllocCpus.title(Duration::hours(12));
+        assert_eq!(title, "Allocated_CPUs_(last_12h)");
+        let title = Metric::JobCount.title(Duration::days(7));
+        assert_eq!(title, "Jobcount_(last_7d)");
+        let title = Metric::JobCount.title(Duration::hours(50));
+        assert_eq!(title, "Jobcount_(last_50h)");
+    }
+
+    #[test]
+    fn test_name_matches_route() {
+        for metric in Metric::ALL {
+            let parsed: Metric = serde_json::from_value(metric.name().into()).unwrap();
+            assert_eq!(parsed, metric);
+        }
+    }
+}
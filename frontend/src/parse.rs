@@ -1,130 +1,151 @@
-use std::{collections::HashMap, num::ParseIntError};
+use std::collections::HashMap;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{bail, Result};
 use itertools::Itertools;
-use thiserror::Error;
+
+#[allow(unused_imports)] // FIXME remove once the frontend shows memory usage
+pub use slurm_monitor_data::size::{FileSize, FileSizeParseError};
+
+/// How to split lines of `sacct` output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvlikeOptions {
+    /// as given to `sacct --delimiter`, `|` by default
+    pub delimiter: char,
+    /// SLURM doesn't escape the delimiter inside fields. If set, surplus fields are joined back
+    /// into the last column instead of rejecting the row. This is a best-effort heuristic: it's
+    /// only correct if the last column (e.g. `JobName`) is the only one containing the delimiter.
+    pub merge_overflow_into_last: bool,
+}
+
+impl Default for CsvlikeOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: '|',
+            merge_overflow_into_last: false,
+        }
+    }
+}
 
 /// Given output from `sacct -P`, parses it into a line vector consisting of HashMaps. This works by taking the first line as header.
 ///
 /// Returns a (header, data) tuple
 pub fn sacct_csvlike(
     input: impl AsRef<str>,
+) -> Result<(Vec<String>, Vec<Result<HashMap<String, String>>>)> {
+    sacct_csvlike_with(input, CsvlikeOptions::default())
+}
+
+/// Like [`sacct_csvlike`], but with custom [`CsvlikeOptions`]
+pub fn sacct_csvlike_with(
+    input: impl AsRef<str>,
+    options: CsvlikeOptions,
 ) -> Result<(Vec<String>, Vec<Result<HashMap<String, String>>>)> {
     let input = input.as_ref();
     let mut lines = input.lines();
     let Some(header) = lines.next() else {
         bail!("data seems to be empty ({input})")
     };
-    let header = header.split('|').map(String::from).collect_vec();
+    let header = header
+        .split(options.delimiter)
+        .map(String::from)
+        .collect_vec();
 
     let data = lines
         .enumerate()
-        .map(|(line_number, line)| {
-            line.split('|')
-                .enumerate()
-                .map(|(i, field)| match header.get(i) {
-                    Some(key) => Ok((String::from(key), String::from(field))),
-                    None => Err(anyhow!(
-                        "Parsing error at line {line_number}: too many fields"
-                    )),
-                })
-                .process_results(|iter| iter.collect::<HashMap<_, _>>())
+        .map(|(i, line)| {
+            let line_number = i + 2; // 1-based, after the header
+            let fields = if options.merge_overflow_into_last {
+                line.splitn(header.len(), options.delimiter).collect_vec()
+            } else {
+                line.split(options.delimiter).collect_vec()
+            };
+            if fields.len() != header.len() {
+                bail!(
+                    "Parsing error at line {line_number}: expected {} fields, found {}",
+                    header.len(),
+                    fields.len()
+                );
+            }
+
+            Ok(header
+                .iter()
+                .cloned()
+                .zip(fields.into_iter().map(String::from))
+                .collect())
         })
         .collect_vec();
 
     Ok((header, data))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct FileSize(pub usize);
-
-#[allow(unused)] // FIXME remove
-impl FileSize {
-    pub fn as_bytes(&self) -> usize {
-        self.0
-    }
-
-    pub fn as_kib(&self) -> f64 {
-        self.0 as f64 / 1024f64
-    }
+#[cfg(test)]
+#[allow(non_snake_case)]
+#[allow(non_upper_case_globals)]
+mod tests {
+    use super::*;
 
-    pub fn as_mib(&self) -> f64 {
-        self.0 as f64 / 1024f64.powi(2)
-    }
+    const ε: f64 = 0.0000000001;
 
-    pub fn as_gib(&self) -> f64 {
-        self.0 as f64 / 1024f64.powi(3)
-    }
+    const SACCT_HEADER: &str = "JobID|JobName|State";
 
-    pub fn as_tib(&self) -> f64 {
-        self.0 as f64 / 1024f64.powi(4)
-    }
-
-    pub fn as_pib(&self) -> f64 {
-        self.0 as f64 / 1024f64.powi(5)
-    }
+    #[test]
+    fn test_sacct_csvlike() {
+        let (header, data) = sacct_csvlike(format!("{SACCT_HEADER}\n1|train|RUNNING")).unwrap();
+        assert_eq!(header, ["JobID", "JobName", "State"]);
 
-    pub fn from_bytes(val: usize) -> Self {
-        Self(val)
+        let job = data[0].as_ref().unwrap();
+        assert_eq!(job["JobID"], "1");
+        assert_eq!(job["JobName"], "train");
+        assert_eq!(job["State"], "RUNNING");
     }
 
-    pub fn from_kib(val: usize) -> Self {
-        Self(val * 1024usize)
+    #[test]
+    fn test_sacct_csvlike_too_many_fields() {
+        let (_, data) = sacct_csvlike(format!("{SACCT_HEADER}\n1|train|RUNNING|extra")).unwrap();
+        let error = data[0].as_ref().unwrap_err().to_string();
+        assert!(
+            error.contains("line 2: expected 3 fields, found 4"),
+            "{error}"
+        );
     }
 
-    pub fn from_mib(val: usize) -> Self {
-        Self(val * 1024usize.pow(2))
+    #[test]
+    fn test_sacct_csvlike_too_few_fields() {
+        let (_, data) = sacct_csvlike(format!("{SACCT_HEADER}\n1|train|RUNNING\n2|eval")).unwrap();
+        assert!(data[0].is_ok());
+        let error = data[1].as_ref().unwrap_err().to_string();
+        assert!(
+            error.contains("line 3: expected 3 fields, found 2"),
+            "{error}"
+        );
     }
 
-    pub fn from_gib(val: usize) -> Self {
-        Self(val * 1024usize.pow(3))
-    }
+    #[test]
+    fn test_sacct_csvlike_delimiter_in_last_column() {
+        let input = "JobID|State|JobName\n1|RUNNING|grep a|b";
+        let options = CsvlikeOptions {
+            merge_overflow_into_last: true,
+            ..Default::default()
+        };
 
-    pub fn from_tib(val: usize) -> Self {
-        Self(val * 1024usize.pow(4))
-    }
+        let (_, data) = sacct_csvlike_with(input, options).unwrap();
+        assert_eq!(data[0].as_ref().unwrap()["JobName"], "grep a|b");
 
-    pub fn from_pib(val: usize) -> Self {
-        Self(val * 1024usize.pow(5))
+        // without merging, the row is rejected instead of silently shifting columns
+        let (_, data) = sacct_csvlike(input).unwrap();
+        assert!(data[0].is_err());
     }
 
-    /// empty means empty after trimming
-    pub fn parse(input: &str) -> Result<FileSize, FileSizeParseError> {
-        use FileSizeParseError::*;
-        let input = input.trim();
-
-        fn strip_suffix(s: &str) -> String {
-            s.chars().dropping_back(1).collect()
-        }
+    #[test]
+    fn test_sacct_csvlike_custom_delimiter() {
+        let options = CsvlikeOptions {
+            delimiter: ';',
+            ..Default::default()
+        };
 
-        match input.chars().last() {
-            Some('K') => Ok(FileSize::from_kib(strip_suffix(input).parse()?)),
-            Some('M') => Ok(FileSize::from_mib(strip_suffix(input).parse()?)),
-            Some('G') => Ok(FileSize::from_gib(strip_suffix(input).parse()?)),
-            Some('T') => Ok(FileSize::from_tib(strip_suffix(input).parse()?)),
-            Some('P') => Ok(FileSize::from_pib(strip_suffix(input).parse()?)),
-            None => Err(Empty),
-            _ => Ok(FileSize::from_bytes(input.parse()?)),
-        }
+        let (_, data) = sacct_csvlike_with("JobID;JobName\n1;a|b", options).unwrap();
+        assert_eq!(data[0].as_ref().unwrap()["JobName"], "a|b");
     }
-}
-
-/// empty means empty after trimming
-#[derive(Debug, Clone, Error)]
-pub enum FileSizeParseError {
-    #[error("trying to parse an empty string")]
-    Empty,
-    #[error("scalar part is not an usize")]
-    InvalidInt(#[from] ParseIntError),
-}
-
-#[cfg(test)]
-#[allow(non_snake_case)]
-#[allow(non_upper_case_globals)]
-mod tests {
-    use super::*;
-
-    const ε: f64 = 0.0000000001;
 
     #[test]
     fn test_parse_bytes() {
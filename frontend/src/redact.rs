@@ -0,0 +1,86 @@
+use std::{borrow::Cow, fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use regex::Regex;
+
+const REPLACEMENT: &str = "[redacted]";
+
+/// Replaces everything matching one of a set of patterns with `[redacted]`
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> Result<Self> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                Regex::new(pattern).with_context(|| format!("compiling pattern `{pattern}`"))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// Loads one pattern per line. Empty lines and lines starting with `#` are ignored.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("reading redaction list {}", path.display()))?;
+
+        Self::new(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        )
+    }
+
+    pub fn redact<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        self.patterns
+            .iter()
+            .fold(Cow::Borrowed(input), |acc, pattern| {
+                if pattern.is_match(&acc) {
+                    Cow::Owned(pattern.replace_all(&acc, REPLACEMENT).into_owned())
+                } else {
+                    acc
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_matching() {
+        let redactor = Redactor::new(["secret-project-\\w+"]).unwrap();
+        assert_eq!(
+            redactor.redact("train_secret-project-x"),
+            "train_[redacted]"
+        );
+    }
+
+    #[test]
+    fn test_redact_passthrough() {
+        let redactor = Redactor::new(["secret-project-\\w+"]).unwrap();
+        assert!(matches!(
+            redactor.redact("train_resnet"),
+            Cow::Borrowed("train_resnet")
+        ));
+    }
+
+    #[test]
+    fn test_redact_empty() {
+        let redactor = Redactor::default();
+        assert_eq!(redactor.redact("anything"), "anything");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Redactor::new(["("]).is_err());
+    }
+}
@@ -29,8 +29,6 @@ pub mod plot {
 
     use super::FONT_FAMILY;
 
-    const JOBCOUNT_OVER_TIME_TITLE: &str = "Jobcount_(last_48h)";
-
     #[allow(non_snake_case)]
     const fn TITLE_FONT_SIZE((w, h): (u32, u32)) -> u32 {
         let avg = w + h / 2;
@@ -75,7 +73,23 @@ pub mod plot {
         })
     }
 
-    pub fn jobcount_over_time<DB>(backend: DB, dataset: &[(NaiveDateTime, usize)]) -> Result<()>
+    /// Distance between x axis labels, so they don't overcrowd for large windows
+    pub fn x_step(window: Duration) -> Duration {
+        if window <= Duration::hours(48) {
+            Duration::hours(1)
+        } else if window <= Duration::weeks(1) {
+            Duration::hours(6)
+        } else {
+            Duration::days(1)
+        }
+    }
+
+    pub fn metric_over_time<DB>(
+        backend: DB,
+        title: &str,
+        x_step: Duration,
+        dataset: &[(NaiveDateTime, f64)],
+    ) -> Result<()>
     where
         DB: DrawingBackend,
         DB::ErrorType: 'static,
@@ -95,13 +109,13 @@ pub mod plot {
 
         let mut chart = ChartBuilder::on(&drawing_area)
             .caption(
-                JOBCOUNT_OVER_TIME_TITLE,
+                title,
                 (FONT_FAMILY, TITLE_FONT_SIZE(drawing_area.dim_in_pixel())).into_font(),
             )
             .margin(5)
             .x_label_area_size(120)
             .y_label_area_size(30)
-            .build_cartesian_2d(coord.step(Duration::hours(1)), min.y..max.y)?;
+            .build_cartesian_2d(coord.step(x_step), min.y..max.y)?;
         chart
             .configure_mesh()
             .x_label_style(